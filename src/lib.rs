@@ -1,5 +1,7 @@
 use bigdecimal::BigDecimal;
+use num_traits::ToPrimitive;
 use std::collections::HashMap;
+use std::fmt;
 use std::iter::Peekable;
 use thiserror::Error;
 
@@ -7,10 +9,14 @@ pub fn eval(
     input: &str,
     context: &mut EvalContext,
     print_parse_tree: bool,
-) -> Result<BigDecimal, EvalError> {
+) -> Result<Value, EvalError> {
     let tokens = lex(input)?;
     let mut token_iter = tokens.into_iter().peekable();
-    let parse_tree = parse_expr(&mut token_iter)?;
+    let parse_tree = if matches!(token_iter.peek().map(|t| &t.kind), Some(TokenKind::Fn)) {
+        parse_fn_def(&mut token_iter)?
+    } else {
+        parse_expr(&mut token_iter)?
+    };
     if print_parse_tree {
         eprintln!("Parse tree:\n{:#?}", parse_tree)
     }
@@ -19,7 +25,47 @@ pub fn eval(
 
 #[derive(Debug, Default)]
 pub struct EvalContext {
-    variables: HashMap<String, BigDecimal>,
+    variables: HashMap<String, Value>,
+    functions: HashMap<String, (Vec<String>, ParseTree)>,
+    call_depth: usize,
+    /// The name of the user-defined function whose body is currently being evaluated, if any,
+    /// so an unresolved variable can be reported as an undefined parameter rather than a bare
+    /// unassigned variable.
+    current_function: Option<String>,
+}
+
+/// User-defined functions are evaluated by recursively calling [`eval_tree`], so a runaway
+/// recursive definition is bounded here rather than being left to overflow the stack. Each
+/// logical call nests several native stack frames (argument evaluation, `eval_tree`, the call
+/// dispatch itself), so this is kept well below a typical thread's stack size rather than some
+/// larger "reasonable recursion depth".
+const MAX_RECURSION_DEPTH: usize = 64;
+
+/// The result of evaluating an expression: a number, the outcome of a comparison/logical
+/// operator, or confirmation that a function definition was recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(BigDecimal),
+    Bool(bool),
+    FunctionDefined(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(num) => write!(f, "{}", num),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::FunctionDefined(name) => write!(f, "defined \"{}\"", name),
+        }
+    }
+}
+
+/// A byte-offset range into the original input, used to underline the offending part of an
+/// expression when reporting an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
 }
 
 #[derive(Error, Debug)]
@@ -30,38 +76,96 @@ pub enum EvalError {
     ParseError(#[from] ParseError),
     #[error("Can not evaluate empty input")]
     EmptyInput,
-    #[error("Variable \"{0}\" has not been assigned")]
-    UnassignedVariable(String),
+    #[error("Variable \"{name}\" has not been assigned")]
+    UnassignedVariable { name: String, span: Span },
+    #[error("\"{name}\" is not a parameter of function \"{function}\"")]
+    UndefinedParameter {
+        name: String,
+        function: String,
+        span: Span,
+    },
+    #[error("Exponent \"{0}\" must be an integer")]
+    NonIntegerExponent(BigDecimal),
+    #[error("Can not raise zero to a negative power")]
+    ZeroToNegativePower,
+    #[error("Unknown function \"{0}\"")]
+    UnknownFunction(String),
+    #[error("Function \"{0}\" expects {1} argument(s), got {2}")]
+    ArityMismatch(String, usize, usize),
+    #[error("{0}")]
+    DomainError(String),
+    #[error("Type mismatch: operand is not of the type the operator expects")]
+    TypeMismatch,
+    #[error("Recursion limit exceeded while calling function \"{0}\"")]
+    RecursionLimitExceeded(String),
+    #[error("Can not compute remainder with a zero modulus")]
+    ModuloByZero,
+}
+
+impl EvalError {
+    /// The span of the input that triggered this error, if one could be determined.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            EvalError::LexError(err) => Some(err.span()),
+            EvalError::ParseError(err) => err.span(),
+            EvalError::UnassignedVariable { span, .. } => Some(*span),
+            EvalError::UndefinedParameter { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum LexError {
-    #[error("The token \"{0}\" is not allowed")]
-    IllegalToken(String),
-    #[error("\"{0}\" is not a number")]
-    IllegalNumber(String),
+    #[error("The token \"{token}\" is not allowed")]
+    IllegalToken { token: String, span: Span },
+    #[error("\"{text}\" is not a number")]
+    IllegalNumber { text: String, span: Span },
     #[error("The input must be ASCII")]
-    NonAsciiInput,
+    NonAsciiInput { span: Span },
+}
+
+impl LexError {
+    fn span(&self) -> Span {
+        match self {
+            LexError::IllegalToken { span, .. } => *span,
+            LexError::IllegalNumber { span, .. } => *span,
+            LexError::NonAsciiInput { span } => *span,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum ParseError {
     #[error("Input contains unmatched parenthesis")]
-    UnmatchedParens,
+    UnmatchedParens { span: Span },
     #[error("Input contains unmatched token")]
-    UnmatchedToken,
+    UnmatchedToken { span: Span },
     #[error("Expected binary operator")]
-    ExpectedBinaryOperator,
+    ExpectedBinaryOperator { span: Span },
     #[error("Can only assign to variable")]
-    ExpectedVariable,
+    ExpectedVariable { span: Span },
     #[error("Can not parse empty input")]
     EmptyInput,
 }
 
+impl ParseError {
+    fn span(&self) -> Option<Span> {
+        match self {
+            ParseError::UnmatchedParens { span }
+            | ParseError::UnmatchedToken { span }
+            | ParseError::ExpectedBinaryOperator { span }
+            | ParseError::ExpectedVariable { span } => Some(*span),
+            ParseError::EmptyInput => None,
+        }
+    }
+}
+
 #[derive(Debug)]
-enum Token {
+enum TokenKind {
     Number(BigDecimal),
     Variable(String),
+    Fn,
     ParenStart,
     ParenClose,
     Assignment,
@@ -69,46 +173,111 @@ enum Token {
     Minus,
     Mul,
     Div,
+    Mod,
+    Caret,
+    Comma,
+    Eq,
+    Neq,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    And,
+    Or,
 }
 
-impl Token {
+impl TokenKind {
     fn op_precedence(&self) -> Option<usize> {
         match self {
-            Token::Assignment => Some(0),
-            Token::Plus | Token::Minus => Some(1),
-            Token::Mul | Token::Div => Some(2),
+            TokenKind::Assignment => Some(0),
+            TokenKind::And | TokenKind::Or => Some(1),
+            TokenKind::Eq
+            | TokenKind::Neq
+            | TokenKind::Lt
+            | TokenKind::Gt
+            | TokenKind::Le
+            | TokenKind::Ge => Some(2),
+            TokenKind::Plus | TokenKind::Minus => Some(3),
+            TokenKind::Mul | TokenKind::Div | TokenKind::Mod => Some(4),
+            TokenKind::Caret => Some(5),
             _ => None,
         }
     }
+
+    /// Whether this operator binds tighter to its right-hand operand than its left,
+    /// e.g. `2 ^ 3 ^ 2` parses as `2 ^ (3 ^ 2)`.
+    fn is_right_associative(&self) -> bool {
+        matches!(self, TokenKind::Caret)
+    }
 }
 
+/// A lexed token together with the byte range of the input it came from, so parse/eval errors
+/// can point back at the offending part of the expression.
 #[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+impl Token {
+    fn op_precedence(&self) -> Option<usize> {
+        self.kind.op_precedence()
+    }
+
+    fn is_right_associative(&self) -> bool {
+        self.kind.is_right_associative()
+    }
+}
+
+#[derive(Debug, Clone)]
 enum ParseTree {
     Number(BigDecimal),
-    Variable(String),
+    Variable(String, Span),
     Neg(Box<ParseTree>),
     Assignment(String, Box<ParseTree>),
+    FnDef(String, Vec<String>, Box<ParseTree>),
     Plus(Box<ParseTree>, Box<ParseTree>),
     Sub(Box<ParseTree>, Box<ParseTree>),
     Mul(Box<ParseTree>, Box<ParseTree>),
     Div(Box<ParseTree>, Box<ParseTree>),
+    Mod(Box<ParseTree>, Box<ParseTree>),
+    Pow(Box<ParseTree>, Box<ParseTree>),
+    Call(String, Vec<ParseTree>),
+    Eq(Box<ParseTree>, Box<ParseTree>),
+    Neq(Box<ParseTree>, Box<ParseTree>),
+    Lt(Box<ParseTree>, Box<ParseTree>),
+    Gt(Box<ParseTree>, Box<ParseTree>),
+    Le(Box<ParseTree>, Box<ParseTree>),
+    Ge(Box<ParseTree>, Box<ParseTree>),
+    And(Box<ParseTree>, Box<ParseTree>),
+    Or(Box<ParseTree>, Box<ParseTree>),
 }
 
 impl ParseTree {
     fn apply(self: Box<Self>, op: Token, other: Box<Self>) -> Result<Box<ParseTree>, ParseError> {
-        let applied = match op {
-            Token::Plus => Self::Plus(self, other),
-            Token::Minus => Self::Sub(self, other),
-            Token::Mul => Self::Mul(self, other),
-            Token::Div => Self::Div(self, other),
-            Token::Assignment => {
-                if let Self::Variable(name) = *self {
+        let applied = match op.kind {
+            TokenKind::Plus => Self::Plus(self, other),
+            TokenKind::Minus => Self::Sub(self, other),
+            TokenKind::Mul => Self::Mul(self, other),
+            TokenKind::Div => Self::Div(self, other),
+            TokenKind::Mod => Self::Mod(self, other),
+            TokenKind::Caret => Self::Pow(self, other),
+            TokenKind::Eq => Self::Eq(self, other),
+            TokenKind::Neq => Self::Neq(self, other),
+            TokenKind::Lt => Self::Lt(self, other),
+            TokenKind::Gt => Self::Gt(self, other),
+            TokenKind::Le => Self::Le(self, other),
+            TokenKind::Ge => Self::Ge(self, other),
+            TokenKind::And => Self::And(self, other),
+            TokenKind::Or => Self::Or(self, other),
+            TokenKind::Assignment => {
+                if let Self::Variable(name, _) = *self {
                     Self::Assignment(name, other)
                 } else {
-                    return Err(ParseError::ExpectedVariable);
+                    return Err(ParseError::ExpectedVariable { span: op.span });
                 }
             }
-            _ => return Err(ParseError::ExpectedBinaryOperator),
+            _ => return Err(ParseError::ExpectedBinaryOperator { span: op.span }),
         };
         Ok(Box::new(applied))
     }
@@ -116,7 +285,12 @@ impl ParseTree {
 
 fn lex(input: &str) -> Result<Vec<Token>, LexError> {
     if !input.is_ascii() {
-        return Err(LexError::NonAsciiInput);
+        return Err(LexError::NonAsciiInput {
+            span: Span {
+                start: 0,
+                end: input.len(),
+            },
+        });
     }
     let mut result = vec![];
 
@@ -126,23 +300,68 @@ fn lex(input: &str) -> Result<Vec<Token>, LexError> {
         if byte.is_ascii_whitespace() {
             continue;
         }
-        let token = match byte {
-            b'(' => Token::ParenStart,
-            b')' => Token::ParenClose,
-            b'+' => Token::Plus,
-            b'-' => Token::Minus,
-            b'*' => Token::Mul,
-            b'/' => Token::Div,
-            b'=' => Token::Assignment,
-            b'0'..=b'9' | b'.' => Token::Number(parse_number(idx, &mut byte_iter, input)?),
-            b'a'..=b'z' | b'_' => Token::Variable(parse_variable(idx, &mut byte_iter, input)),
+        let kind = match byte {
+            b'(' => TokenKind::ParenStart,
+            b')' => TokenKind::ParenClose,
+            b'+' => TokenKind::Plus,
+            b'-' => TokenKind::Minus,
+            b'*' => TokenKind::Mul,
+            b'/' => TokenKind::Div,
+            b'%' => TokenKind::Mod,
+            b'^' => TokenKind::Caret,
+            b',' => TokenKind::Comma,
+            b'&' => TokenKind::And,
+            b'|' => TokenKind::Or,
+            b'=' if matches!(byte_iter.peek(), Some((_, b'='))) => {
+                byte_iter.next();
+                TokenKind::Eq
+            }
+            b'=' => TokenKind::Assignment,
+            b'!' if matches!(byte_iter.peek(), Some((_, b'='))) => {
+                byte_iter.next();
+                TokenKind::Neq
+            }
+            b'<' if matches!(byte_iter.peek(), Some((_, b'='))) => {
+                byte_iter.next();
+                TokenKind::Le
+            }
+            b'<' => TokenKind::Lt,
+            b'>' if matches!(byte_iter.peek(), Some((_, b'='))) => {
+                byte_iter.next();
+                TokenKind::Ge
+            }
+            b'>' => TokenKind::Gt,
+            b'0' if matches!(byte_iter.peek(), Some((_, b'x' | b'b' | b'o'))) => {
+                TokenKind::Number(parse_radix_number(idx, &mut byte_iter, input)?)
+            }
+            b'0'..=b'9' | b'.' => TokenKind::Number(parse_number(idx, &mut byte_iter, input)?),
+            b'a'..=b'z' | b'_' => {
+                let name = parse_variable(idx, &mut byte_iter, input);
+                if name == "fn" {
+                    TokenKind::Fn
+                } else {
+                    TokenKind::Variable(name)
+                }
+            }
             unknown => {
-                return Err(LexError::IllegalToken(
-                    String::from_utf8_lossy(&[unknown]).into_owned(),
-                ))
+                let end_idx = byte_iter.peek().map(|(i, _)| *i).unwrap_or(input.len());
+                return Err(LexError::IllegalToken {
+                    token: String::from_utf8_lossy(&[unknown]).into_owned(),
+                    span: Span {
+                        start: idx,
+                        end: end_idx,
+                    },
+                });
             }
         };
-        result.push(token);
+        let end_idx = byte_iter.peek().map(|(i, _)| *i).unwrap_or(input.len());
+        result.push(Token {
+            kind,
+            span: Span {
+                start: idx,
+                end: end_idx,
+            },
+        });
     }
 
     Ok(result)
@@ -162,12 +381,62 @@ fn parse_number(
             break;
         }
     }
-    let number = input[start_idx..end_idx]
-        .parse()
-        .map_err(|_| LexError::IllegalNumber(input[start_idx..end_idx].to_string()))?;
+    let number = input[start_idx..end_idx].parse().map_err(|_| {
+        LexError::IllegalNumber {
+            text: input[start_idx..end_idx].to_string(),
+            span: Span {
+                start: start_idx,
+                end: end_idx,
+            },
+        }
+    })?;
     Ok(number)
 }
 
+/// Parses a radix-prefixed integer literal (`0x`, `0b`, `0o`) starting at the `0` of the
+/// prefix. The digits after the prefix are parsed as an `i128` and converted to `BigDecimal`,
+/// since there is no fractional form for these literals.
+fn parse_radix_number(
+    start_idx: usize,
+    byte_iter: &mut Peekable<impl Iterator<Item = (usize, u8)>>,
+    input: &str,
+) -> Result<BigDecimal, LexError> {
+    let (_, prefix) = byte_iter.next().expect("guarded by caller");
+    let (radix, is_radix_digit): (u32, fn(u8) -> bool) = match prefix {
+        b'x' => (16, |byte| byte.is_ascii_hexdigit()),
+        b'b' => (2, |byte| matches!(byte, b'0' | b'1')),
+        b'o' => (8, |byte| matches!(byte, b'0'..=b'7')),
+        _ => unreachable!("guarded by caller"),
+    };
+
+    let mut end_idx = start_idx + 2;
+    while let Some((_, byte)) = byte_iter.peek() {
+        if is_radix_digit(*byte) {
+            byte_iter.next();
+            end_idx += 1;
+        } else {
+            break;
+        }
+    }
+
+    let digits = &input[start_idx + 2..end_idx];
+    let span = Span {
+        start: start_idx,
+        end: end_idx,
+    };
+    if digits.is_empty() {
+        return Err(LexError::IllegalNumber {
+            text: input[start_idx..end_idx].to_string(),
+            span,
+        });
+    }
+    let value = i128::from_str_radix(digits, radix).map_err(|_| LexError::IllegalNumber {
+        text: input[start_idx..end_idx].to_string(),
+        span,
+    })?;
+    Ok(BigDecimal::from(value))
+}
+
 fn parse_variable(
     start_idx: usize,
     byte_iter: &mut Peekable<impl Iterator<Item = (usize, u8)>>,
@@ -175,7 +444,7 @@ fn parse_variable(
 ) -> String {
     let mut end_idx = start_idx + 1;
     while let Some((_, byte)) = byte_iter.peek() {
-        if matches!(byte, b'a'..=b'z' | b'-') {
+        if matches!(byte, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-') {
             byte_iter.next();
             end_idx += 1;
         } else {
@@ -186,22 +455,25 @@ fn parse_variable(
 }
 
 fn parse_expr(
-    input: &mut Peekable<impl DoubleEndedIterator<Item = Token>>,
+    input: &mut Peekable<impl Iterator<Item = Token>>,
 ) -> Result<Box<ParseTree>, ParseError> {
     parse_expr_rec(parse_primary(input)?, input, 0)
 }
 
 fn parse_expr_rec(
     mut lhs: Box<ParseTree>,
-    input: &mut Peekable<impl DoubleEndedIterator<Item = Token>>,
+    input: &mut Peekable<impl Iterator<Item = Token>>,
     min_precedence: usize,
 ) -> Result<Box<ParseTree>, ParseError> {
     let mut lookahead = input.peek();
-    while lookahead.map(Token::op_precedence).flatten() >= Some(min_precedence) {
+    while lookahead.and_then(Token::op_precedence) >= Some(min_precedence) {
         let op = input.next().unwrap();
         let mut rhs = parse_primary(input)?;
         lookahead = input.peek();
-        while lookahead.map(Token::op_precedence).flatten() > op.op_precedence() {
+        while lookahead.and_then(Token::op_precedence) > op.op_precedence()
+            || (op.is_right_associative()
+                && lookahead.and_then(Token::op_precedence) == op.op_precedence())
+        {
             let lookahead_prec = lookahead.unwrap().op_precedence().unwrap();
             rhs = parse_expr_rec(rhs, input, lookahead_prec)?;
             lookahead = input.peek();
@@ -212,46 +484,360 @@ fn parse_expr_rec(
 }
 
 fn parse_primary(
-    input: &mut Peekable<impl DoubleEndedIterator<Item = Token>>,
+    input: &mut Peekable<impl Iterator<Item = Token>>,
 ) -> Result<Box<ParseTree>, ParseError> {
     match input.next() {
-        Some(Token::ParenStart) => {
-            if let Some(Token::ParenClose) = input.next_back() {
-                parse_expr(input)
-            } else {
-                Err(ParseError::UnmatchedParens)
+        Some(token) => match token.kind {
+            TokenKind::ParenStart => {
+                let inner = parse_expr(input)?;
+                match input.next() {
+                    Some(close) if matches!(close.kind, TokenKind::ParenClose) => Ok(inner),
+                    _ => Err(ParseError::UnmatchedParens { span: token.span }),
+                }
             }
-        }
-        Some(Token::Number(num)) => Ok(Box::new(ParseTree::Number(num))),
-        Some(Token::Minus) => Ok(Box::new(ParseTree::Neg(parse_primary(input)?))),
-        Some(Token::Variable(name)) => Ok(Box::new(ParseTree::Variable(name))),
-        Some(_) => Err(ParseError::UnmatchedToken),
+            TokenKind::Number(num) => Ok(Box::new(ParseTree::Number(num))),
+            TokenKind::Minus => {
+                // Unary minus binds looser than `^` (so `-3 ^ 2` is `-(3 ^ 2) == -9`, matching
+                // the usual mathematical convention) but tighter than the other binary
+                // operators (so `-3 * 2` is still `(-3) * 2`). Parsing the operand at `^`'s own
+                // precedence lets it absorb a following exponent chain before negating.
+                let caret_precedence = TokenKind::Caret
+                    .op_precedence()
+                    .expect("Caret always has a precedence");
+                let operand = parse_expr_rec(parse_primary(input)?, input, caret_precedence)?;
+                Ok(Box::new(ParseTree::Neg(operand)))
+            }
+            TokenKind::Variable(name)
+                if matches!(input.peek().map(|t| &t.kind), Some(TokenKind::ParenStart)) =>
+            {
+                let open_paren = input.next().unwrap();
+                let args = parse_call_args(input, open_paren.span)?;
+                Ok(Box::new(ParseTree::Call(name, args)))
+            }
+            TokenKind::Variable(name) => Ok(Box::new(ParseTree::Variable(name, token.span))),
+            _ => Err(ParseError::UnmatchedToken { span: token.span }),
+        },
         None => Err(ParseError::EmptyInput),
     }
 }
 
-fn eval_tree(parse_tree: &ParseTree, context: &mut EvalContext) -> Result<BigDecimal, EvalError> {
+fn parse_call_args(
+    input: &mut Peekable<impl Iterator<Item = Token>>,
+    open_paren_span: Span,
+) -> Result<Vec<ParseTree>, ParseError> {
+    let mut args = vec![];
+    if matches!(input.peek().map(|t| &t.kind), Some(TokenKind::ParenClose)) {
+        input.next();
+        return Ok(args);
+    }
+    loop {
+        args.push(*parse_expr(input)?);
+        match input.next() {
+            Some(token) if matches!(token.kind, TokenKind::Comma) => continue,
+            Some(token) if matches!(token.kind, TokenKind::ParenClose) => break,
+            _ => {
+                return Err(ParseError::UnmatchedParens {
+                    span: open_paren_span,
+                })
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Parses a function definition of the form `fn name(param, ...) = body`, starting after the
+/// `fn` keyword has already been peeked by the caller.
+fn parse_fn_def(
+    input: &mut Peekable<impl Iterator<Item = Token>>,
+) -> Result<Box<ParseTree>, ParseError> {
+    input.next(); // the `fn` keyword
+    let name = match input.next() {
+        Some(token) => match token.kind {
+            TokenKind::Variable(name) => name,
+            _ => return Err(ParseError::ExpectedVariable { span: token.span }),
+        },
+        None => return Err(ParseError::EmptyInput),
+    };
+    let open_paren = match input.next() {
+        Some(token) if matches!(token.kind, TokenKind::ParenStart) => token,
+        Some(token) => return Err(ParseError::UnmatchedParens { span: token.span }),
+        None => return Err(ParseError::EmptyInput),
+    };
+    let params = parse_fn_params(input, open_paren.span)?;
+    match input.next() {
+        Some(token) if matches!(token.kind, TokenKind::Assignment) => {}
+        Some(token) => return Err(ParseError::ExpectedBinaryOperator { span: token.span }),
+        None => return Err(ParseError::EmptyInput),
+    }
+    let body = parse_expr(input)?;
+    Ok(Box::new(ParseTree::FnDef(name, params, body)))
+}
+
+/// Parses the comma-separated parameter list of a function definition, up to and including the
+/// closing `)`. Mirrors [`parse_call_args`], but collects bare parameter names instead of
+/// expressions.
+fn parse_fn_params(
+    input: &mut Peekable<impl Iterator<Item = Token>>,
+    open_paren_span: Span,
+) -> Result<Vec<String>, ParseError> {
+    let mut params = vec![];
+    if matches!(input.peek().map(|t| &t.kind), Some(TokenKind::ParenClose)) {
+        input.next();
+        return Ok(params);
+    }
+    loop {
+        match input.next() {
+            Some(token) => match token.kind {
+                TokenKind::Variable(name) => params.push(name),
+                _ => return Err(ParseError::ExpectedVariable { span: token.span }),
+            },
+            None => {
+                return Err(ParseError::UnmatchedParens {
+                    span: open_paren_span,
+                })
+            }
+        }
+        match input.next() {
+            Some(token) if matches!(token.kind, TokenKind::Comma) => continue,
+            Some(token) if matches!(token.kind, TokenKind::ParenClose) => break,
+            _ => {
+                return Err(ParseError::UnmatchedParens {
+                    span: open_paren_span,
+                })
+            }
+        }
+    }
+    Ok(params)
+}
+
+fn eval_tree(parse_tree: &ParseTree, context: &mut EvalContext) -> Result<Value, EvalError> {
     let result = match parse_tree {
-        ParseTree::Number(num) => num.clone(),
-        ParseTree::Variable(name) => context
-            .variables
-            .get(name)
-            .cloned()
-            .ok_or_else(|| EvalError::UnassignedVariable(name.clone()))?,
+        ParseTree::Number(num) => Value::Number(num.clone()),
+        ParseTree::Variable(name, span) => {
+            context.variables.get(name).cloned().ok_or_else(|| {
+                match &context.current_function {
+                    Some(function) => EvalError::UndefinedParameter {
+                        name: name.clone(),
+                        function: function.clone(),
+                        span: *span,
+                    },
+                    None => EvalError::UnassignedVariable {
+                        name: name.clone(),
+                        span: *span,
+                    },
+                }
+            })?
+        }
         ParseTree::Assignment(name, tree) => {
             let result = eval_tree(tree, context)?;
             context.variables.insert(name.clone(), result.clone());
             result
         }
-        ParseTree::Neg(tree) => -eval_tree(tree, context)?,
-        ParseTree::Plus(left, right) => eval_tree(left, context)? + eval_tree(right, context)?,
-        ParseTree::Sub(left, right) => eval_tree(left, context)? - eval_tree(right, context)?,
-        ParseTree::Mul(left, right) => eval_tree(left, context)? * eval_tree(right, context)?,
-        ParseTree::Div(left, right) => eval_tree(left, context)? / eval_tree(right, context)?,
+        ParseTree::Neg(tree) => Value::Number(-expect_number(eval_tree(tree, context)?)?),
+        ParseTree::Plus(left, right) => Value::Number(
+            expect_number(eval_tree(left, context)?)? + expect_number(eval_tree(right, context)?)?,
+        ),
+        ParseTree::Sub(left, right) => Value::Number(
+            expect_number(eval_tree(left, context)?)? - expect_number(eval_tree(right, context)?)?,
+        ),
+        ParseTree::Mul(left, right) => Value::Number(
+            expect_number(eval_tree(left, context)?)? * expect_number(eval_tree(right, context)?)?,
+        ),
+        ParseTree::Div(left, right) => Value::Number(
+            expect_number(eval_tree(left, context)?)? / expect_number(eval_tree(right, context)?)?,
+        ),
+        ParseTree::Mod(left, right) => Value::Number(modulo(
+            expect_number(eval_tree(left, context)?)?,
+            expect_number(eval_tree(right, context)?)?,
+        )?),
+        ParseTree::Pow(base, exp) => Value::Number(pow(
+            expect_number(eval_tree(base, context)?)?,
+            expect_number(eval_tree(exp, context)?)?,
+        )?),
+        ParseTree::Call(name, arg_trees) => {
+            let args = arg_trees
+                .iter()
+                .map(|tree| expect_number(eval_tree(tree, context)?))
+                .collect::<Result<Vec<_>, _>>()?;
+            match context.functions.get(name).cloned() {
+                Some((params, body)) => {
+                    Value::Number(eval_user_function(name, &params, &body, args, context)?)
+                }
+                None => Value::Number(call_function(name, args)?),
+            }
+        }
+        ParseTree::FnDef(name, params, body) => {
+            context
+                .functions
+                .insert(name.clone(), (params.clone(), *body.clone()));
+            Value::FunctionDefined(name.clone())
+        }
+        ParseTree::Eq(left, right) => {
+            Value::Bool(eval_tree(left, context)? == eval_tree(right, context)?)
+        }
+        ParseTree::Neq(left, right) => {
+            Value::Bool(eval_tree(left, context)? != eval_tree(right, context)?)
+        }
+        ParseTree::Lt(left, right) => Value::Bool(
+            expect_number(eval_tree(left, context)?)? < expect_number(eval_tree(right, context)?)?,
+        ),
+        ParseTree::Gt(left, right) => Value::Bool(
+            expect_number(eval_tree(left, context)?)? > expect_number(eval_tree(right, context)?)?,
+        ),
+        ParseTree::Le(left, right) => Value::Bool(
+            expect_number(eval_tree(left, context)?)?
+                <= expect_number(eval_tree(right, context)?)?,
+        ),
+        ParseTree::Ge(left, right) => Value::Bool(
+            expect_number(eval_tree(left, context)?)?
+                >= expect_number(eval_tree(right, context)?)?,
+        ),
+        ParseTree::And(left, right) => Value::Bool(
+            expect_bool(eval_tree(left, context)?)? && expect_bool(eval_tree(right, context)?)?,
+        ),
+        ParseTree::Or(left, right) => Value::Bool(
+            expect_bool(eval_tree(left, context)?)? || expect_bool(eval_tree(right, context)?)?,
+        ),
     };
     Ok(result)
 }
 
+fn expect_number(value: Value) -> Result<BigDecimal, EvalError> {
+    match value {
+        Value::Number(num) => Ok(num),
+        Value::Bool(_) | Value::FunctionDefined(_) => Err(EvalError::TypeMismatch),
+    }
+}
+
+fn expect_bool(value: Value) -> Result<bool, EvalError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        Value::Number(_) | Value::FunctionDefined(_) => Err(EvalError::TypeMismatch),
+    }
+}
+
+/// Evaluates a call to a user-defined function in a fresh child scope: a clone of the caller's
+/// variables with the parameters bound to `args`, so the function body can see enclosing
+/// variables but any locals it assigns are discarded once the call returns. Recursive calls are
+/// bounded by [`MAX_RECURSION_DEPTH`] to fail gracefully instead of overflowing the stack. A
+/// variable in the body that is neither a parameter nor an existing global surfaces as
+/// [`EvalError::UndefinedParameter`].
+fn eval_user_function(
+    name: &str,
+    params: &[String],
+    body: &ParseTree,
+    args: Vec<BigDecimal>,
+    context: &mut EvalContext,
+) -> Result<BigDecimal, EvalError> {
+    if args.len() != params.len() {
+        return Err(EvalError::ArityMismatch(
+            name.to_string(),
+            params.len(),
+            args.len(),
+        ));
+    }
+    if context.call_depth >= MAX_RECURSION_DEPTH {
+        return Err(EvalError::RecursionLimitExceeded(name.to_string()));
+    }
+    let mut variables = context.variables.clone();
+    for (param, arg) in params.iter().zip(args) {
+        variables.insert(param.clone(), Value::Number(arg));
+    }
+    let mut local_context = EvalContext {
+        variables,
+        functions: context.functions.clone(),
+        call_depth: context.call_depth + 1,
+        current_function: Some(name.to_string()),
+    };
+    expect_number(eval_tree(body, &mut local_context)?)
+}
+
+/// Dispatches a call expression against the built-in function table.
+fn call_function(name: &str, args: Vec<BigDecimal>) -> Result<BigDecimal, EvalError> {
+    match name {
+        "sqrt" => {
+            let arg = unary_arg(name, &args)?;
+            arg.sqrt().ok_or_else(|| {
+                EvalError::DomainError(format!(
+                    "square root of negative number \"{}\" is undefined",
+                    arg
+                ))
+            })
+        }
+        "abs" => Ok(unary_arg(name, &args)?.abs()),
+        "floor" => Ok(floor(unary_arg(name, &args)?)),
+        "ceil" => Ok(ceil(unary_arg(name, &args)?)),
+        "min" => args
+            .into_iter()
+            .reduce(|a, b| if b < a { b } else { a })
+            .ok_or_else(|| EvalError::ArityMismatch(name.to_string(), 1, 0)),
+        "max" => args
+            .into_iter()
+            .reduce(|a, b| if b > a { b } else { a })
+            .ok_or_else(|| EvalError::ArityMismatch(name.to_string(), 1, 0)),
+        _ => Err(EvalError::UnknownFunction(name.to_string())),
+    }
+}
+
+fn unary_arg<'a>(name: &str, args: &'a [BigDecimal]) -> Result<&'a BigDecimal, EvalError> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(EvalError::ArityMismatch(name.to_string(), 1, args.len())),
+    }
+}
+
+fn floor(num: &BigDecimal) -> BigDecimal {
+    let rounded = num.with_scale(0);
+    if &rounded > num {
+        rounded - BigDecimal::from(1)
+    } else {
+        rounded
+    }
+}
+
+fn ceil(num: &BigDecimal) -> BigDecimal {
+    let rounded = num.with_scale(0);
+    if &rounded < num {
+        rounded + BigDecimal::from(1)
+    } else {
+        rounded
+    }
+}
+
+/// Computes `dividend % divisor` using `BigDecimal`'s remainder semantics, which follow the
+/// sign of the dividend (truncated division) just like Rust's primitive `%`: e.g. `-7 % 3 == -1`
+/// and `7 % -3 == 1`.
+fn modulo(dividend: BigDecimal, divisor: BigDecimal) -> Result<BigDecimal, EvalError> {
+    if divisor == 0 {
+        return Err(EvalError::ModuloByZero);
+    }
+    Ok(dividend % divisor)
+}
+
+/// Raises `base` to `exp`. `BigDecimal` has no native fractional exponentiation, so `exp`
+/// must represent an integer; negative exponents are computed as the reciprocal of the
+/// corresponding positive power.
+fn pow(base: BigDecimal, exp: BigDecimal) -> Result<BigDecimal, EvalError> {
+    let exp_int = exp
+        .to_i64()
+        .filter(|int_exp| exp == *int_exp)
+        .ok_or_else(|| EvalError::NonIntegerExponent(exp.clone()))?;
+
+    if exp_int < 0 {
+        if base == 0 {
+            return Err(EvalError::ZeroToNegativePower);
+        }
+        let positive_power = pow(base, BigDecimal::from(-exp_int))?;
+        return Ok(BigDecimal::from(1) / positive_power);
+    }
+
+    let mut result = BigDecimal::from(1);
+    for _ in 0..exp_int {
+        result *= &base;
+    }
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,6 +889,85 @@ mod tests {
         assert_eq!(expected, parse_number(0, &mut byte_iter, input).unwrap());
     }
 
+    #[test]
+    fn test_parse_hex_number() {
+        let input = "0xff";
+        let mut byte_iter = input.bytes().enumerate().peekable();
+        byte_iter.next();
+        let expected: BigDecimal = 255.try_into().unwrap();
+        assert_eq!(
+            expected,
+            parse_radix_number(0, &mut byte_iter, input).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_binary_number() {
+        let input = "0b101";
+        let mut byte_iter = input.bytes().enumerate().peekable();
+        byte_iter.next();
+        let expected: BigDecimal = 5.try_into().unwrap();
+        assert_eq!(
+            expected,
+            parse_radix_number(0, &mut byte_iter, input).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_octal_number() {
+        let input = "0o17";
+        let mut byte_iter = input.bytes().enumerate().peekable();
+        byte_iter.next();
+        let expected: BigDecimal = 15.try_into().unwrap();
+        assert_eq!(
+            expected,
+            parse_radix_number(0, &mut byte_iter, input).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_hex_number_errors() {
+        let input = "0x";
+        let mut byte_iter = input.bytes().enumerate().peekable();
+        byte_iter.next();
+        assert!(matches!(
+            parse_radix_number(0, &mut byte_iter, input),
+            Err(LexError::IllegalNumber { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_malformed_binary_number_errors() {
+        let input = "0b2";
+        let mut byte_iter = input.bytes().enumerate().peekable();
+        byte_iter.next();
+        assert!(matches!(
+            parse_radix_number(0, &mut byte_iter, input),
+            Err(LexError::IllegalNumber { .. })
+        ));
+    }
+
+    #[test]
+    fn compute_hex_literal() -> Result<(), EvalError> {
+        let expected = Value::Number(255.0.try_into().unwrap());
+        assert_eq!(expected, eval("0xff", &mut EvalContext::default(), true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn compute_plain_zero_still_decimal() -> Result<(), EvalError> {
+        let expected = Value::Number(0.0.try_into().unwrap());
+        assert_eq!(expected, eval("0", &mut EvalContext::default(), true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn compute_zero_point_five_still_decimal() -> Result<(), EvalError> {
+        let expected = Value::Number(0.5.try_into().unwrap());
+        assert_eq!(expected, eval("0.5", &mut EvalContext::default(), true)?);
+        Ok(())
+    }
+
     #[test]
     fn parse_simple_expr() {
         let input = lex("42").unwrap();
@@ -319,7 +984,7 @@ mod tests {
 
     #[test]
     fn compute_sub() -> Result<(), EvalError> {
-        let expected: BigDecimal = 0.try_into().unwrap();
+        let expected = Value::Number(0.try_into().unwrap());
         assert_eq!(
             expected,
             eval("42 - 42", &mut EvalContext::default(), true)?
@@ -329,21 +994,65 @@ mod tests {
 
     #[test]
     fn compute_mul() -> Result<(), EvalError> {
-        let expected: BigDecimal = 84.0.try_into().unwrap();
+        let expected = Value::Number(84.0.try_into().unwrap());
         assert_eq!(expected, eval("2 * 42", &mut EvalContext::default(), true)?);
         Ok(())
     }
 
     #[test]
     fn compute_div() -> Result<(), EvalError> {
-        let expected: BigDecimal = 21.0.try_into().unwrap();
+        let expected = Value::Number(21.0.try_into().unwrap());
         assert_eq!(expected, eval("42 / 2", &mut EvalContext::default(), true)?);
         Ok(())
     }
 
+    #[test]
+    fn compute_mod() -> Result<(), EvalError> {
+        let expected = Value::Number(1.0.try_into().unwrap());
+        assert_eq!(expected, eval("7 % 3", &mut EvalContext::default(), true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn compute_mod_negative_dividend() -> Result<(), EvalError> {
+        let expected = Value::Number((-1.0).try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("-7 % 3", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_mod_negative_divisor() -> Result<(), EvalError> {
+        let expected = Value::Number(1.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("7 % -3", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_mod_same_precedence_as_mul() -> Result<(), EvalError> {
+        // 2 * 5 % 4 == (2 * 5) % 4 == 2, left-to-right at equal precedence
+        let expected = Value::Number(2.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("2 * 5 % 4", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_mod_by_zero_errors() {
+        let result = eval("1 % 0", &mut EvalContext::default(), true);
+        assert!(matches!(result, Err(EvalError::ModuloByZero)));
+    }
+
     #[test]
     fn compute_with_precedence() -> Result<(), EvalError> {
-        let expected: BigDecimal = 25.0.try_into().unwrap();
+        let expected = Value::Number(25.0.try_into().unwrap());
         assert_eq!(
             expected,
             eval("5 + 10 * 2", &mut EvalContext::default(), true)?
@@ -353,7 +1062,7 @@ mod tests {
 
     #[test]
     fn compute_with_braces() -> Result<(), EvalError> {
-        let expected: BigDecimal = 42.0.try_into().unwrap();
+        let expected = Value::Number(42.0.try_into().unwrap());
         assert_eq!(
             expected,
             eval("2 * (10 + 11)", &mut EvalContext::default(), true)?
@@ -363,7 +1072,7 @@ mod tests {
 
     #[test]
     fn compute_negation() -> Result<(), EvalError> {
-        let expected: BigDecimal = (-42.0).try_into().unwrap();
+        let expected = Value::Number((-42.0).try_into().unwrap());
         assert_eq!(
             expected,
             eval("-2 * (10 + 11)", &mut EvalContext::default(), true)?
@@ -373,7 +1082,7 @@ mod tests {
 
     #[test]
     fn set_variable() -> Result<(), EvalError> {
-        let expected: BigDecimal = (666.0).try_into().unwrap();
+        let expected = Value::Number((666.0).try_into().unwrap());
         assert_eq!(
             expected,
             eval("devil = 666", &mut EvalContext::default(), true)?
@@ -383,17 +1092,17 @@ mod tests {
 
     #[test]
     fn set_and_use_variable() -> Result<(), EvalError> {
-        let expected: BigDecimal = (666.0).try_into().unwrap();
+        let expected = Value::Number((666.0).try_into().unwrap());
         let mut context = EvalContext::default();
         assert_eq!(expected, eval("devil = 666", &mut context, true)?);
-        let expected: BigDecimal = (0.0).try_into().unwrap();
+        let expected = Value::Number((0.0).try_into().unwrap());
         assert_eq!(expected, eval("devil - 666", &mut context, true)?);
         Ok(())
     }
 
     #[test]
     fn compute_alternating_add_sub() -> Result<(), EvalError> {
-        let expected: BigDecimal = (5.0).try_into().unwrap();
+        let expected = Value::Number((5.0).try_into().unwrap());
         assert_eq!(
             expected,
             eval("5 - 5 + 5", &mut EvalContext::default(), true)?
@@ -401,13 +1110,336 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn compute_pow() -> Result<(), EvalError> {
+        let expected = Value::Number(8.0.try_into().unwrap());
+        assert_eq!(expected, eval("2 ^ 3", &mut EvalContext::default(), true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn compute_pow_right_associative() -> Result<(), EvalError> {
+        // 2 ^ (3 ^ 2) = 2 ^ 9 = 512, not (2 ^ 3) ^ 2 = 64
+        let expected = Value::Number(512.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("2 ^ 3 ^ 2", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_pow_negative_exponent() -> Result<(), EvalError> {
+        let expected = Value::Number(0.25.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("2 ^ -2", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_pow_binds_tighter_than_unary_minus() -> Result<(), EvalError> {
+        // -3 ^ 2 == -(3 ^ 2) == -9, not (-3) ^ 2 == 9
+        let expected = Value::Number((-9.0).try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("-3 ^ 2", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_pow_binds_tighter_than_unary_minus_in_sum() -> Result<(), EvalError> {
+        // 2 + -3 ^ 2 == 2 + -(3 ^ 2) == -7, not 2 + (-3) ^ 2 == 11
+        let expected = Value::Number((-7.0).try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("2 + -3 ^ 2", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_pow_non_integer_exponent_errors() {
+        let result = eval("2 ^ 0.5", &mut EvalContext::default(), true);
+        assert!(matches!(result, Err(EvalError::NonIntegerExponent(_))));
+    }
+
+    #[test]
+    fn compute_zero_to_negative_power_errors() {
+        let result = eval("0 ^ -1", &mut EvalContext::default(), true);
+        assert!(matches!(result, Err(EvalError::ZeroToNegativePower)));
+    }
+
+    #[test]
+    fn compute_sqrt() -> Result<(), EvalError> {
+        let expected = Value::Number(3.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("sqrt(9)", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_abs() -> Result<(), EvalError> {
+        let expected = Value::Number(3.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("abs(-3)", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_min() -> Result<(), EvalError> {
+        let expected = Value::Number(4.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("min(4, 9)", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_max() -> Result<(), EvalError> {
+        let expected = Value::Number(9.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("max(4, 9, 2)", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_call_with_parenthesized_non_final_argument() -> Result<(), EvalError> {
+        let expected = Value::Number(10.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("max((1 + 2), 10)", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_floor() -> Result<(), EvalError> {
+        let expected = Value::Number(2.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("floor(2.7)", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_ceil() -> Result<(), EvalError> {
+        let expected = Value::Number(3.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("ceil(2.1)", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_call_in_expression() -> Result<(), EvalError> {
+        let expected = Value::Number(4.0.try_into().unwrap());
+        assert_eq!(
+            expected,
+            eval("sqrt(9) + 1", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_unknown_function_errors() {
+        let result = eval("frobnicate(1)", &mut EvalContext::default(), true);
+        assert!(matches!(
+            result,
+            Err(EvalError::UnknownFunction(name)) if name == "frobnicate"
+        ));
+    }
+
+    #[test]
+    fn compute_arity_mismatch_errors() {
+        let result = eval("sqrt(1, 2)", &mut EvalContext::default(), true);
+        assert!(matches!(result, Err(EvalError::ArityMismatch(_, 1, 2))));
+    }
+
     #[test]
     fn compute_check_precision() -> Result<(), EvalError> {
-        let expected: BigDecimal = 0.3.try_into().unwrap();
+        let expected = Value::Number(0.3.try_into().unwrap());
         assert_eq!(
             expected,
             eval("0.1 + 0.2", &mut EvalContext::default(), true)?
         );
         Ok(())
     }
+
+    #[test]
+    fn compute_less_than() -> Result<(), EvalError> {
+        assert_eq!(
+            Value::Bool(true),
+            eval("3 < 5", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_greater_than_or_equal() -> Result<(), EvalError> {
+        assert_eq!(
+            Value::Bool(false),
+            eval("3 >= 5", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_equality() -> Result<(), EvalError> {
+        assert_eq!(
+            Value::Bool(true),
+            eval("1 + 1 == 2", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_inequality() -> Result<(), EvalError> {
+        assert_eq!(
+            Value::Bool(true),
+            eval("1 != 2", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_logical_and() -> Result<(), EvalError> {
+        assert_eq!(
+            Value::Bool(true),
+            eval("3 < 5 & 5 < 10", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_logical_or() -> Result<(), EvalError> {
+        assert_eq!(
+            Value::Bool(true),
+            eval("3 > 5 | 5 < 10", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_arithmetic_on_bool_errors() {
+        let mut context = EvalContext::default();
+        eval("flag = 1 < 2", &mut context, true).unwrap();
+        let result = eval("flag + 1", &mut context, true);
+        assert!(matches!(result, Err(EvalError::TypeMismatch)));
+    }
+
+    #[test]
+    fn compute_unassigned_variable_span_points_at_variable() {
+        let result = eval("1 + foo", &mut EvalContext::default(), true);
+        match result {
+            Err(EvalError::UnassignedVariable { name, span }) => {
+                assert_eq!(name, "foo");
+                assert_eq!(span, Span { start: 4, end: 7 });
+            }
+            other => panic!("expected UnassignedVariable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lex_illegal_token_span_points_at_offending_char() {
+        let result = lex("1 + @");
+        match result {
+            Err(LexError::IllegalToken { token, span }) => {
+                assert_eq!(token, "@");
+                assert_eq!(span, Span { start: 4, end: 5 });
+            }
+            other => panic!("expected IllegalToken, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_unmatched_parens_span_points_at_open_paren() {
+        let tokens = lex("(1 + 2").unwrap();
+        let result = parse_expr(&mut tokens.into_iter().peekable());
+        match result {
+            Err(ParseError::UnmatchedParens { span }) => {
+                assert_eq!(span, Span { start: 0, end: 1 });
+            }
+            other => panic!("expected UnmatchedParens, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn define_and_call_user_function() -> Result<(), EvalError> {
+        let mut context = EvalContext::default();
+        eval("fn square(x) = x * x", &mut context, true)?;
+        let expected = Value::Number(25.0.try_into().unwrap());
+        assert_eq!(expected, eval("square(5)", &mut context, true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn fn_def_returns_function_defined_value() -> Result<(), EvalError> {
+        let expected = Value::FunctionDefined("square".to_string());
+        assert_eq!(
+            expected,
+            eval("fn square(x) = x * x", &mut EvalContext::default(), true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn user_function_sees_global_variables() -> Result<(), EvalError> {
+        let mut context = EvalContext::default();
+        eval("offset = 10", &mut context, true)?;
+        eval("fn add_offset(x) = x + offset", &mut context, true)?;
+        let expected = Value::Number(15.0.try_into().unwrap());
+        assert_eq!(expected, eval("add_offset(5)", &mut context, true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn user_function_locals_do_not_leak_into_global_scope() -> Result<(), EvalError> {
+        let mut context = EvalContext::default();
+        eval("x = 1", &mut context, true)?;
+        eval("fn square(x) = x * x", &mut context, true)?;
+        eval("square(5)", &mut context, true)?;
+        let expected = Value::Number(1.0.try_into().unwrap());
+        assert_eq!(expected, eval("x", &mut context, true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn user_function_arity_mismatch_errors() {
+        let mut context = EvalContext::default();
+        eval("fn square(x) = x * x", &mut context, true).unwrap();
+        let result = eval("square(1, 2)", &mut context, true);
+        assert!(matches!(result, Err(EvalError::ArityMismatch(_, 1, 2))));
+    }
+
+    #[test]
+    fn user_function_undefined_parameter_errors() {
+        let mut context = EvalContext::default();
+        eval("fn broken(x) = y * y", &mut context, true).unwrap();
+        let result = eval("broken(1)", &mut context, true);
+        assert!(matches!(
+            result,
+            Err(EvalError::UndefinedParameter { name, function, .. })
+                if name == "y" && function == "broken"
+        ));
+    }
+
+    #[test]
+    fn user_function_recursion_limit_errors() {
+        let mut context = EvalContext::default();
+        eval("fn loop_forever(x) = loop_forever(x)", &mut context, true).unwrap();
+        let result = eval("loop_forever(1)", &mut context, true);
+        assert!(matches!(result, Err(EvalError::RecursionLimitExceeded(_))));
+    }
 }
@@ -16,6 +16,11 @@ fn handle_input(input: &str, context: &mut EvalContext, opts: &Opts) {
     match eval(input, context, opts.print_parse_tree) {
         Ok(result) => println!("{}", result),
         Err(err) => {
+            if let Some(span) = err.span() {
+                eprintln!("{}", input.trim_end_matches('\n'));
+                let width = (span.end - span.start).max(1);
+                eprintln!("{}{}", " ".repeat(span.start), "^".repeat(width));
+            }
             let err: anyhow::Error = err.into();
             eprintln!("{:#}", err);
         }